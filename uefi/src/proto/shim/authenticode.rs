@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Rust-side Authenticode certificate-table parser.
+//!
+//! There is a known class of shim vulnerability where an attacker zeroes the
+//! `WIN_CERTIFICATE.dwLength` field so that blacklist checks and Authenticode
+//! verification disagree about the signature's extent. [`parse_authenticode`]
+//! locates the PE Certificate Table via the optional-header data directory and
+//! walks the `WIN_CERTIFICATE` entries, cross-checking that each entry's
+//! embedded `dwLength` is consistent with the directory-reported size and the
+//! overall buffer bounds. Truncated, zero-length, or overlapping certificates
+//! are rejected here — at the Rust layer, regardless of the shim version
+//! underneath — before [`verify`](super::ShimLock::verify) is ever called.
+
+use crate::result::Error;
+use crate::{Result, Status};
+
+fn security_violation() -> Error {
+    Status::SECURITY_VIOLATION.into()
+}
+
+// Offset of the `e_lfanew` field in the DOS header.
+const DOS_E_LFANEW: usize = 0x3c;
+// `PE\0\0` signature length plus the COFF file header length.
+const PE_SIGNATURE_SIZE: usize = 4;
+const COFF_HEADER_SIZE: usize = 20;
+// Optional-header magic values.
+const PE32_MAGIC: u16 = 0x010b;
+const PE32_PLUS_MAGIC: u16 = 0x020b;
+// Offset of the data-directory array within the optional header.
+const PE32_DATA_DIR_OFFSET: usize = 96;
+const PE32_PLUS_DATA_DIR_OFFSET: usize = 112;
+// Index of the Certificate Table entry in the data directory.
+const CERTIFICATE_DIR_INDEX: usize = 4;
+// `WIN_CERTIFICATE` header: dwLength (4) + wRevision (2) + wCertificateType (2).
+const WIN_CERT_HEADER_SIZE: usize = 8;
+
+fn read_u16(buffer: &[u8], offset: usize) -> Result<u16> {
+    buffer
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| Status::INVALID_PARAMETER.into())
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> Result<u32> {
+    buffer
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| Status::INVALID_PARAMETER.into())
+}
+
+/// A single validated `WIN_CERTIFICATE` entry from the PE Certificate Table.
+#[derive(Debug)]
+pub struct Certificate<'a> {
+    /// Offset of the entry within the original buffer.
+    pub offset: usize,
+    /// `wRevision` field.
+    pub revision: u16,
+    /// `wCertificateType` field.
+    pub certificate_type: u16,
+    /// Certificate payload (the `bCertificate` bytes, excluding the header).
+    pub data: &'a [u8],
+}
+
+/// A validated view of a PE image's Certificate Table.
+///
+/// Produced by [`parse_authenticode`]. The byte range and per-entry offsets are
+/// guaranteed to lie within the buffer and to agree with the directory-reported
+/// size, so callers can safely hand the same range to
+/// [`verify`](super::ShimLock::verify) and [`hash`](super::ShimLock::hash).
+#[derive(Debug)]
+pub struct CertificateTable<'a> {
+    offset: usize,
+    table: &'a [u8],
+}
+
+impl<'a> CertificateTable<'a> {
+    /// Offset of the Certificate Table within the original buffer.
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The raw, bounds-checked Certificate Table bytes.
+    #[must_use]
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.table
+    }
+
+    /// Iterate over the validated `WIN_CERTIFICATE` entries.
+    #[must_use]
+    pub const fn entries(&self) -> Certificates<'a> {
+        Certificates {
+            base: self.offset,
+            table: self.table,
+            pos: 0,
+        }
+    }
+}
+
+/// Iterator over the entries of a [`CertificateTable`].
+#[derive(Debug)]
+pub struct Certificates<'a> {
+    base: usize,
+    table: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Certificates<'a> {
+    type Item = Certificate<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.table.len() {
+            return None;
+        }
+        // Bounds were fully validated by `parse_authenticode`, so these reads
+        // cannot fail.
+        let entry = &self.table[self.pos..];
+        let length = u32::from_le_bytes(entry[0..4].try_into().unwrap()) as usize;
+        let revision = u16::from_le_bytes(entry[4..6].try_into().unwrap());
+        let certificate_type = u16::from_le_bytes(entry[6..8].try_into().unwrap());
+
+        let cert = Certificate {
+            offset: self.base + self.pos,
+            revision,
+            certificate_type,
+            data: &entry[WIN_CERT_HEADER_SIZE..length],
+        };
+
+        // Entries are padded to the next 8-byte boundary.
+        self.pos += (length + 7) & !7;
+        Some(cert)
+    }
+}
+
+/// Locate and validate the Authenticode Certificate Table of a PE image.
+///
+/// Returns the table's bounds-checked byte range, or a
+/// [`Status::SECURITY_VIOLATION`] error when the embedded `WIN_CERTIFICATE`
+/// lengths are truncated, zero, or overlap — the `dwLength`-tampering pattern
+/// that desynchronizes blacklist checks from Authenticode verification.
+pub fn parse_authenticode(buffer: &[u8]) -> Result<CertificateTable<'_>> {
+    // `MZ` DOS magic; `e_lfanew` is only meaningful once it is present.
+    if buffer.get(0..2) != Some(b"MZ") {
+        return Err(Status::INVALID_PARAMETER.into());
+    }
+
+    let pe_offset = read_u32(buffer, DOS_E_LFANEW)? as usize;
+
+    // `PE\0\0` signature.
+    if buffer.get(pe_offset..pe_offset + PE_SIGNATURE_SIZE) != Some(b"PE\0\0") {
+        return Err(Status::INVALID_PARAMETER.into());
+    }
+
+    let optional_header = pe_offset + PE_SIGNATURE_SIZE + COFF_HEADER_SIZE;
+    let data_dir_offset = match read_u16(buffer, optional_header)? {
+        PE32_MAGIC => optional_header + PE32_DATA_DIR_OFFSET,
+        PE32_PLUS_MAGIC => optional_header + PE32_PLUS_DATA_DIR_OFFSET,
+        _ => return Err(Status::INVALID_PARAMETER.into()),
+    };
+
+    let entry = data_dir_offset + CERTIFICATE_DIR_INDEX * 8;
+    let table_offset = read_u32(buffer, entry)? as usize;
+    let table_size = read_u32(buffer, entry + 4)? as usize;
+
+    if table_offset == 0 || table_size == 0 {
+        return Err(Status::NOT_FOUND.into());
+    }
+
+    // The directory-reported range must lie wholly within the buffer.
+    let table = buffer
+        .get(table_offset..table_offset + table_size)
+        .ok_or_else(security_violation)?;
+
+    // Walk every `WIN_CERTIFICATE` and cross-check its `dwLength` against the
+    // directory size and buffer bounds, rejecting any inconsistency.
+    let mut pos = 0usize;
+    while pos < table_size {
+        if table_size - pos < WIN_CERT_HEADER_SIZE {
+            return Err(security_violation());
+        }
+        let length = u32::from_le_bytes(table[pos..pos + 4].try_into().unwrap()) as usize;
+        // A zeroed or header-only length is the exact bypass we guard against,
+        // and the entry must fit in what the directory claimed.
+        if length < WIN_CERT_HEADER_SIZE || length > table_size - pos {
+            return Err(security_violation());
+        }
+        // Advance with 8-byte padding; the padding may not run past the table.
+        let padded = (length + 7) & !7;
+        if padded > table_size - pos {
+            // The final entry may omit trailing padding.
+            if length == table_size - pos {
+                break;
+            }
+            return Err(security_violation());
+        }
+        pos += padded;
+    }
+
+    Ok(CertificateTable {
+        offset: table_offset,
+        table,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    // Certificate Table lives immediately after the data directory we write.
+    const TABLE_OFFSET: usize = 224;
+
+    // Build a minimal PE32 image whose Certificate Table directory points at
+    // `table`, appended right after the headers.
+    fn pe_with_table(table: &[u8]) -> Vec<u8> {
+        let mut buf = vec![0u8; TABLE_OFFSET];
+        buf[0..2].copy_from_slice(b"MZ");
+        // e_lfanew -> PE signature at offset 64.
+        buf[DOS_E_LFANEW..DOS_E_LFANEW + 4].copy_from_slice(&64u32.to_le_bytes());
+        buf[64..68].copy_from_slice(b"PE\0\0");
+        // Optional-header magic (PE32) at pe + sig + COFF header.
+        let optional_header = 64 + PE_SIGNATURE_SIZE + COFF_HEADER_SIZE;
+        buf[optional_header..optional_header + 2].copy_from_slice(&PE32_MAGIC.to_le_bytes());
+        // Certificate Table data-directory entry: offset then size.
+        let entry = optional_header + PE32_DATA_DIR_OFFSET + CERTIFICATE_DIR_INDEX * 8;
+        buf[entry..entry + 4].copy_from_slice(&(TABLE_OFFSET as u32).to_le_bytes());
+        buf[entry + 4..entry + 8].copy_from_slice(&(table.len() as u32).to_le_bytes());
+        buf.extend_from_slice(table);
+        buf
+    }
+
+    // A `WIN_CERTIFICATE` with the given embedded `dwLength`, padded to the next
+    // 8-byte boundary. `dwLength` is passed explicitly so tests can tamper it.
+    fn win_cert(dw_length: u32, payload: &[u8]) -> Vec<u8> {
+        let mut cert = Vec::new();
+        cert.extend_from_slice(&dw_length.to_le_bytes());
+        cert.extend_from_slice(&0x0200u16.to_le_bytes()); // wRevision
+        cert.extend_from_slice(&0x0002u16.to_le_bytes()); // wCertificateType
+        cert.extend_from_slice(payload);
+        while cert.len() % 8 != 0 {
+            cert.push(0);
+        }
+        cert
+    }
+
+    #[test]
+    fn single_valid_certificate() {
+        let payload = [0xAAu8; 8];
+        let cert = win_cert((WIN_CERT_HEADER_SIZE + payload.len()) as u32, &payload);
+        let image = pe_with_table(&cert);
+
+        let table = parse_authenticode(&image).unwrap();
+        assert_eq!(table.offset(), TABLE_OFFSET);
+        let entries: Vec<_> = table.entries().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].offset, TABLE_OFFSET);
+        assert_eq!(entries[0].certificate_type, 0x0002);
+        assert_eq!(entries[0].data, &payload);
+    }
+
+    #[test]
+    fn zeroed_dwlength_is_rejected() {
+        let cert = win_cert(0, &[0xAAu8; 8]);
+        let image = pe_with_table(&cert);
+        assert_eq!(
+            parse_authenticode(&image).unwrap_err().status(),
+            Status::SECURITY_VIOLATION
+        );
+    }
+
+    #[test]
+    fn short_dwlength_is_rejected() {
+        // Smaller than the 8-byte header.
+        let cert = win_cert(4, &[0xAAu8; 8]);
+        let image = pe_with_table(&cert);
+        assert_eq!(
+            parse_authenticode(&image).unwrap_err().status(),
+            Status::SECURITY_VIOLATION
+        );
+    }
+
+    #[test]
+    fn overlapping_dwlength_is_rejected() {
+        // `dwLength` claims more than the directory-reported table size.
+        let payload = [0xAAu8; 8];
+        let cert = win_cert((WIN_CERT_HEADER_SIZE + payload.len() + 64) as u32, &payload);
+        let image = pe_with_table(&cert);
+        assert_eq!(
+            parse_authenticode(&image).unwrap_err().status(),
+            Status::SECURITY_VIOLATION
+        );
+    }
+
+    #[test]
+    fn final_entry_without_padding() {
+        // `dwLength` is not a multiple of 8 and the table ends exactly there, so
+        // the trailing padding is legitimately absent.
+        let payload = [0xAAu8; 5];
+        let length = (WIN_CERT_HEADER_SIZE + payload.len()) as u32;
+        let mut cert = Vec::new();
+        cert.extend_from_slice(&length.to_le_bytes());
+        cert.extend_from_slice(&0x0200u16.to_le_bytes());
+        cert.extend_from_slice(&0x0002u16.to_le_bytes());
+        cert.extend_from_slice(&payload);
+        assert_eq!(cert.len() % 8, 5);
+        let image = pe_with_table(&cert);
+
+        let entries: Vec<_> = parse_authenticode(&image).unwrap().entries().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].data, &payload);
+    }
+
+    #[test]
+    fn missing_mz_magic_is_rejected() {
+        let cert = win_cert((WIN_CERT_HEADER_SIZE + 8) as u32, &[0xAAu8; 8]);
+        let mut image = pe_with_table(&cert);
+        image[0] = 0;
+        image[1] = 0;
+        assert_eq!(
+            parse_authenticode(&image).unwrap_err().status(),
+            Status::INVALID_PARAMETER
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Load a target image *through* shim via `LoadImage`.
+//!
+//! Based on the iPXE approach of "executing images via a shim": this lets a
+//! caller run a Secure-Boot-validated image without embedding a full
+//! second-stage loader like GRUB solely to reach the shim lock protocol.
+//!
+//! [`chainload`] loads the shim binary with [`boot::load_image`] and, before
+//! returning its handle, (a) installs a `LoadFile2` instance plus
+//! a device path on a fresh handle and hands that device path to `load_image`
+//! as shim's file path, so shim's loaded-image `DeviceHandle` resolves back to
+//! our handle and shim reads the target bytes through `LoadFile2`, and (b)
+//! prepends the selected image's path to shim's command line via the
+//! [`LoadedImage`] `load_options`. Because the handle is created fresh it
+//! carries none of the PXE-related protocols shim probes first, so shim falls
+//! through to the in-memory buffer rather than trying to re-download the image.
+//!
+//! [`LoadedImage`]: uefi::proto::loaded_image::LoadedImage
+
+use crate::proto::device_path::build::{self, DevicePathBuilder};
+use crate::proto::device_path::DevicePath;
+use crate::proto::loaded_image::LoadedImage;
+use crate::proto::unsafe_protocol;
+use crate::result::Error;
+use crate::{boot, CStr16, Handle, Status, StatusExt};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use boot::LoadImageSource;
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// `EFI_LOAD_FILE2_PROTOCOL`.
+#[repr(C)]
+#[unsafe_protocol("4006c0c1-fcb3-403e-996d-4a6c8724e06d")]
+struct LoadFile2 {
+    load_file: unsafe extern "efiapi" fn(
+        this: *mut LoadFile2,
+        file_path: *const DevicePath,
+        boot_policy: bool,
+        buffer_size: *mut usize,
+        buffer: *mut c_void,
+    ) -> Status,
+}
+
+// Cell that hands out a raw pointer to its contents instead of a reference, so
+// the target-image static below never needs `&mut <static>` (the
+// `static_mut_refs` lint). This mirrors the `Racy` cell in [`super::security`];
+// it is sound only because boot services are single-threaded and at most one
+// chainload is in flight at a time.
+struct Racy<T>(UnsafeCell<T>);
+
+// SAFETY: access is serialized by single-threaded boot services.
+unsafe impl<T> Sync for Racy<T> {}
+
+impl<T> Racy<T> {
+    const fn new(value: T) -> Self {
+        Self(UnsafeCell::new(value))
+    }
+
+    fn get(&self) -> *mut T {
+        self.0.get()
+    }
+}
+
+// The installed [`LoadFile2`] handler carries no user context, so it reaches
+// the target image through this static.
+static TARGET_IMAGE: Racy<&[u8]> = Racy::new(&[]);
+
+unsafe extern "efiapi" fn serve_target(
+    _this: *mut LoadFile2,
+    _file_path: *const DevicePath,
+    _boot_policy: bool,
+    buffer_size: *mut usize,
+    buffer: *mut c_void,
+) -> Status {
+    // SAFETY: set by `chainload` before the protocol is installed.
+    let image = unsafe { *TARGET_IMAGE.get() };
+    if buffer_size.is_null() {
+        return Status::INVALID_PARAMETER;
+    }
+    // SAFETY: the firmware always passes a valid size pointer.
+    let provided = unsafe { *buffer_size };
+    // SAFETY: ditto; report the required size to a caller with a small buffer.
+    unsafe { *buffer_size = image.len() };
+    if buffer.is_null() || provided < image.len() {
+        return Status::BUFFER_TOO_SMALL;
+    }
+    // SAFETY: the caller guaranteed `buffer` holds at least `image.len()` bytes.
+    unsafe {
+        ptr::copy_nonoverlapping(image.as_ptr(), buffer.cast::<u8>(), image.len());
+    }
+    Status::SUCCESS
+}
+
+/// Options controlling how a target image is chainloaded through shim.
+#[derive(Debug)]
+pub struct ChainloadOptions<'a> {
+    /// Path handed to shim on its command line so it knows which image to load.
+    pub target_path: &'a CStr16,
+}
+
+impl<'a> ChainloadOptions<'a> {
+    /// Create options for the given target path.
+    #[must_use]
+    pub const fn new(target_path: &'a CStr16) -> Self {
+        Self { target_path }
+    }
+}
+
+/// Load `target` through the `shim` binary and return the handle of the loaded
+/// shim image.
+///
+/// The returned image is loaded but not started; the caller drives it with
+/// [`boot::start_image`]. Both buffers must remain valid until shim has
+/// consumed them, i.e. until the loaded image has started.
+pub fn chainload(
+    shim: &[u8],
+    target: &'static [u8],
+    options: ChainloadOptions<'_>,
+) -> crate::Result<Handle> {
+    // Expose the target bytes to the `LoadFile2` handler before installing it.
+    // SAFETY: single-threaded boot services; see the static above.
+    unsafe {
+        *TARGET_IMAGE.get() = target;
+    }
+
+    // Install `LoadFile2` on a fresh handle so shim can read the hidden file.
+    // The interface must outlive this call — shim dereferences it during
+    // `start_image`, long after `chainload` returns — so leak it rather than
+    // registering a stack local that the firmware would be left pointing at.
+    let load_file2: &'static mut LoadFile2 = Box::leak(Box::new(LoadFile2 {
+        load_file: serve_target,
+    }));
+    let device_handle = unsafe {
+        boot::install_protocol_interface(
+            None,
+            &LoadFile2::GUID,
+            (load_file2 as *mut LoadFile2).cast::<c_void>(),
+        )?
+    };
+
+    // Give that handle a device path ending in the target's file name and
+    // install it, then hand the same path to `load_image` as shim's file path.
+    // The firmware resolves it back to `device_handle`, so shim's loaded-image
+    // `DeviceHandle` is our handle and its `LoadFile2` probe lands on
+    // `serve_target`. The buffer is leaked for the same lifetime reason as the
+    // interface above.
+    let dp_buf: &'static mut [MaybeUninit<u8>] =
+        Vec::leak(vec![MaybeUninit::uninit(); 256 + options.target_path.num_bytes()]);
+    let device_path = DevicePathBuilder::with_buf(dp_buf)
+        .push(&build::media::FilePath {
+            path_name: options.target_path,
+        })
+        .and_then(DevicePathBuilder::finalize)
+        .map_err(|_| Error::from(Status::OUT_OF_RESOURCES))?;
+    unsafe {
+        boot::install_protocol_interface(
+            Some(device_handle),
+            &DevicePath::GUID,
+            device_path.as_ffi_ptr().cast::<c_void>(),
+        )?;
+    }
+
+    // The handle is created fresh, so it carries none of the PXE-related
+    // protocols (`EFI_PXE_BASE_CODE_PROTOCOL`, `EFI_LOAD_FILE_PROTOCOL`) shim
+    // probes first; shim therefore falls through to the in-memory buffer.
+
+    // Load shim from its buffer, recording the target's device path so shim's
+    // loaded-image `DeviceHandle` resolves to the handle that serves it.
+    let image = boot::load_image(
+        boot::image_handle(),
+        LoadImageSource::FromBuffer {
+            buffer: shim,
+            file_path: Some(device_path),
+        },
+    )?;
+
+    // Prepend the target's path to shim's command line via `load_options`.
+    let mut loaded_image = boot::open_protocol_exclusive::<LoadedImage>(image)?;
+    let mut options_buf: Vec<u16> = options.target_path.as_slice_with_nul().to_vec();
+    let bytes = options_buf.len() * core::mem::size_of::<u16>();
+    // SAFETY: the options buffer lives as long as `loaded_image`, and shim
+    // copies the command line out during `start_image`.
+    unsafe {
+        loaded_image.set_load_options(options_buf.as_mut_ptr().cast::<u8>(), bytes as u32);
+    }
+    // Keep the buffer alive for the duration of the loaded image.
+    core::mem::forget(options_buf);
+
+    Ok(image)
+}
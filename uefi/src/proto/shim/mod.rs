@@ -9,27 +9,35 @@
     target_arch = "aarch64"
 ))]
 
+pub mod authenticode;
+#[cfg(feature = "alloc")]
+pub mod chainload;
+pub mod mok;
+pub mod security;
+
 use crate::proto::unsafe_protocol;
 use crate::result::Error;
 use crate::{Result, Status, StatusExt};
 use core::ffi::c_void;
 use core::mem::MaybeUninit;
 
-// The `PE_COFF_LOADER_IMAGE_CONTEXT` type. None of our methods need to inspect
-// the fields of this struct, we just need to make sure it is the right size.
+// The `PE_COFF_LOADER_IMAGE_CONTEXT` type. Most of our methods only use this
+// struct as scratch space for the `context`/`hash` callbacks, but
+// [`ShimLock::read_context`] reads its scalar fields out of it, so they are
+// named rather than `_`-prefixed.
 #[repr(C)]
 struct Context {
-    _image_address: u64,
-    _image_size: u64,
-    _entry_point: u64,
-    _size_of_headers: usize,
-    _image_type: u16,
-    _number_of_sections: u16,
-    _section_alignment: u32,
+    image_address: u64,
+    image_size: u64,
+    entry_point: u64,
+    size_of_headers: usize,
+    image_type: u16,
+    number_of_sections: u16,
+    section_alignment: u32,
     _first_section: *const c_void,
     _reloc_dir: *const c_void,
     _sec_dir: *const c_void,
-    _number_of_rva_and_sizes: u64,
+    number_of_rva_and_sizes: u64,
     _pe_hdr: *const c_void,
 }
 
@@ -45,6 +53,33 @@ pub struct Hashes {
     pub sha1: [u8; SHA1_DIGEST_SIZE],
 }
 
+/// Image information that shim parsed out of a PE/COFF application.
+///
+/// This is an owned, safe view of the scalar fields of shim's internal
+/// `PE_COFF_LOADER_IMAGE_CONTEXT`, produced by [`ShimLock::read_context`]. It
+/// lets callers inspect where shim believes the image should be loaded and
+/// where its entry point and sections are without re-parsing the PE
+/// themselves.
+#[derive(Debug)]
+pub struct ImageContext {
+    /// Preferred load address of the image.
+    pub image_address: u64,
+    /// Size of the image in memory.
+    pub image_size: u64,
+    /// Address of the image entry point.
+    pub entry_point: u64,
+    /// Size of the PE/COFF headers.
+    pub size_of_headers: usize,
+    /// Image subsystem type (`IMAGE_SUBSYSTEM_*`).
+    pub image_type: u16,
+    /// Number of sections in the image.
+    pub number_of_sections: u16,
+    /// Section alignment in bytes.
+    pub section_alignment: u32,
+    /// Number of entries in the optional-header data directory.
+    pub number_of_rva_and_sizes: u64,
+}
+
 // These macros set the correct calling convention for the Shim protocol methods.
 
 #[cfg(target_arch = "x86")]
@@ -128,4 +163,38 @@ impl ShimLock {
         )
         .to_result()
     }
+    /// Read the PE/COFF image context that shim parses out of the provided EFI
+    /// application.
+    ///
+    /// This invokes shim's `context` callback and returns a safe, owned
+    /// [`ImageContext`] describing where shim thinks the image should be loaded
+    /// and where its entry point and sections are. Callers that want to
+    /// relocate or measure an image after validating it can use this instead of
+    /// re-parsing the PE headers by hand.
+    ///
+    /// The buffer's size must fit in a `u32`; if that condition is not met then
+    /// a `BAD_BUFFER_SIZE` error will be returned and the shim lock protocol
+    /// will not be called.
+    pub fn read_context(&self, buffer: &[u8]) -> Result<ImageContext> {
+        let size: u32 = buffer
+            .len()
+            .try_into()
+            .map_err(|_| Error::from(Status::BAD_BUFFER_SIZE))?;
+
+        let mut context = MaybeUninit::<Context>::uninit();
+        (self.context)(buffer.as_ptr(), size, context.as_mut_ptr()).to_result()?;
+        // SAFETY: the `context` callback succeeded, so shim has fully
+        // initialized the struct.
+        let context = unsafe { context.assume_init() };
+        Ok(ImageContext {
+            image_address: context.image_address,
+            image_size: context.image_size,
+            entry_point: context.entry_point,
+            size_of_headers: context.size_of_headers,
+            image_type: context.image_type,
+            number_of_sections: context.number_of_sections,
+            section_alignment: context.section_alignment,
+            number_of_rva_and_sizes: context.number_of_rva_and_sizes,
+        })
+    }
 }
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Machine Owner Key (MOK) variable accessors.
+//!
+//! Validating a chained image against shim requires more than the
+//! [`verify`](super::ShimLock::verify) callback: loaders need to read shim's
+//! Machine Owner Key databases to make their own trust decisions and to honor
+//! the platform's Secure Boot state.
+//!
+//! This module exposes typed accessors for the runtime variables shim
+//! publishes under its vendor GUID — `MokListRT` and `MokListXRT` (enrolled and
+//! revoked keys) and `MokSBStateRT` (whether shim's MOK verification is
+//! disabled) — parsing the `EFI_SIGNATURE_LIST`/`EFI_SIGNATURE_DATA` layout
+//! into an iterator of [`SignatureList`] records. [`secure_mode`] reports
+//! whether the firmware itself is enforcing Secure Boot.
+
+use crate::runtime::{self, VariableVendor};
+use crate::{cstr16, guid, CStr16, Guid, Status};
+
+/// Vendor GUID under which shim stores its runtime MOK variables.
+///
+/// This is the same GUID used by the [`ShimLock`](super::ShimLock) protocol.
+pub const SHIM_LOCK_GUID: Guid = guid!("605dab50-e046-4300-abb6-3dd810dd8b23");
+
+const MOK_LIST_RT: &CStr16 = cstr16!("MokListRT");
+const MOK_LIST_X_RT: &CStr16 = cstr16!("MokListXRT");
+const MOK_SB_STATE_RT: &CStr16 = cstr16!("MokSBStateRT");
+
+const SECURE_BOOT: &CStr16 = cstr16!("SecureBoot");
+const SETUP_MODE: &CStr16 = cstr16!("SetupMode");
+
+/// A single signature record parsed from a MOK database.
+///
+/// `owner` is the GUID of the entity that enrolled the key and `data` is the
+/// raw signature payload (for example, a DER-encoded X.509 certificate or a
+/// SHA-256 hash), borrowed from the caller's variable buffer.
+#[derive(Debug)]
+pub struct SignatureList<'a> {
+    /// GUID identifying the agent that owns this signature.
+    pub owner: Guid,
+    /// Raw signature bytes.
+    pub data: &'a [u8],
+}
+
+/// Iterator over the signatures stored in a MOK database variable.
+///
+/// Walks the concatenated `EFI_SIGNATURE_LIST` structures and yields one
+/// [`SignatureList`] per contained `EFI_SIGNATURE_DATA` record.
+#[derive(Debug)]
+pub struct Signatures<'a> {
+    // Remaining bytes of the current signature list (its records only).
+    records: &'a [u8],
+    signature_size: usize,
+    // Bytes of variable data following the current list.
+    rest: &'a [u8],
+}
+
+impl<'a> Signatures<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let mut this = Self {
+            records: &[],
+            signature_size: 0,
+            rest: data,
+        };
+        this.advance_list();
+        this
+    }
+
+    // Parse the header of the next `EFI_SIGNATURE_LIST` in `rest`, skipping its
+    // per-list header and leaving the records ready to yield.
+    fn advance_list(&mut self) {
+        while self.records.is_empty() {
+            // Header: SignatureType (16) + SignatureListSize (4) +
+            // SignatureHeaderSize (4) + SignatureSize (4) = 28 bytes.
+            if self.rest.len() < 28 {
+                self.rest = &[];
+                return;
+            }
+            let list_size = u32::from_le_bytes(self.rest[16..20].try_into().unwrap()) as usize;
+            let header_size = u32::from_le_bytes(self.rest[20..24].try_into().unwrap()) as usize;
+            let signature_size = u32::from_le_bytes(self.rest[24..28].try_into().unwrap()) as usize;
+
+            // A malformed list would otherwise loop forever or overrun. Each
+            // record must be at least the 16-byte owner GUID, or `next` would
+            // index past the record slicing out that GUID.
+            if list_size < 28 + header_size || list_size > self.rest.len() || signature_size < 16 {
+                self.rest = &[];
+                return;
+            }
+
+            let records = &self.rest[28 + header_size..list_size];
+            self.rest = &self.rest[list_size..];
+            self.signature_size = signature_size;
+            self.records = records;
+        }
+    }
+}
+
+impl<'a> Iterator for Signatures<'a> {
+    type Item = SignatureList<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.records.len() < self.signature_size {
+            return None;
+        }
+        let record = &self.records[..self.signature_size];
+        self.records = &self.records[self.signature_size..];
+
+        // Each record is an `EFI_SIGNATURE_DATA`: a 16-byte owner GUID followed
+        // by the signature payload.
+        let owner = Guid::from_bytes(record[..16].try_into().unwrap());
+        let data = &record[16..];
+
+        let item = SignatureList { owner, data };
+        self.advance_list();
+        Some(item)
+    }
+}
+
+fn read_signatures<'buf>(
+    name: &CStr16,
+    buf: &'buf mut [u8],
+) -> crate::Result<Signatures<'buf>> {
+    let (data, _) = runtime::get_variable(name, &VariableVendor(SHIM_LOCK_GUID), buf)?;
+    Ok(Signatures::new(data))
+}
+
+/// Read the enrolled Machine Owner Keys from `MokListRT`.
+pub fn enrolled_keys(buf: &mut [u8]) -> crate::Result<Signatures<'_>> {
+    read_signatures(MOK_LIST_RT, buf)
+}
+
+/// Read the revoked (blacklisted) Machine Owner Keys from `MokListXRT`.
+pub fn revoked_keys(buf: &mut [u8]) -> crate::Result<Signatures<'_>> {
+    read_signatures(MOK_LIST_X_RT, buf)
+}
+
+/// Report whether shim's MOK verification has been disabled via `MokSBStateRT`.
+///
+/// Returns `Ok(true)` when the variable is present and non-zero (the user opted
+/// out of shim's validation), and `Ok(false)` when it is absent or zero.
+pub fn mok_verification_disabled() -> crate::Result<bool> {
+    let mut buf = [0u8; 1];
+    match runtime::get_variable(MOK_SB_STATE_RT, &VariableVendor(SHIM_LOCK_GUID), &mut buf) {
+        Ok((data, _)) => Ok(data.first().is_some_and(|&b| b != 0)),
+        Err(e) if e.status() == Status::NOT_FOUND => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Report whether the platform is in Secure Boot "secure mode".
+///
+/// This reads the global `SecureBoot` and `SetupMode` variables: the platform
+/// is enforcing Secure Boot when `SecureBoot` is enabled and `SetupMode` is
+/// not active.
+pub fn secure_mode() -> crate::Result<bool> {
+    let mut buf = [0u8; 1];
+    let (secure_boot, _) =
+        runtime::get_variable(SECURE_BOOT, &VariableVendor::GLOBAL_VARIABLE, &mut buf)?;
+    let secure_boot = secure_boot.first().copied().unwrap_or(0) != 0;
+
+    let mut buf = [0u8; 1];
+    let setup_mode = match runtime::get_variable(
+        SETUP_MODE,
+        &VariableVendor::GLOBAL_VARIABLE,
+        &mut buf,
+    ) {
+        Ok((data, _)) => data.first().copied().unwrap_or(0) != 0,
+        Err(e) if e.status() == Status::NOT_FOUND => false,
+        Err(e) => return Err(e),
+    };
+
+    Ok(secure_boot && !setup_mode)
+}
@@ -0,0 +1,277 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Shim-backed UEFI security policy override.
+//!
+//! Second-stage loaders (systemd-boot, the PreLoader-derived rEFInd code,
+//! efitools, …) gain Secure Boot support by installing a UEFI security policy
+//! so that the firmware's own [`BootServices::load_image`] path is validated
+//! against shim's embedded certificate and MOK list, instead of every loader
+//! calling [`ShimLock::verify`] by hand.
+//!
+//! [`SecurityOverride`] wraps the architectural
+//! `EFI_SECURITY_ARCH_PROTOCOL` and `EFI_SECURITY2_ARCH_PROTOCOL` instances
+//! published by the firmware. It saves their original authentication function
+//! pointers, swaps in replacements that try the original policy first, and
+//! restores the originals when the returned guard is dropped. On failure, only
+//! the `EFI_SECURITY2_ARCH_PROTOCOL` replacement falls back to
+//! [`ShimLock::verify`]; the `EFI_SECURITY_ARCH_PROTOCOL` replacement does not
+//! read the file behind its device path and returns the original verdict.
+//!
+//! [`BootServices::load_image`]: uefi::boot::load_image
+
+use super::ShimLock;
+use crate::proto::device_path::DevicePath;
+use crate::proto::unsafe_protocol;
+use crate::{boot, Status, StatusExt};
+use boot::{OpenProtocolAttributes, OpenProtocolParams};
+use core::cell::UnsafeCell;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// `EFI_SECURITY_ARCH_PROTOCOL`.
+#[repr(C)]
+#[unsafe_protocol("a46423e3-4617-49f1-b9ff-d1bfa9115839")]
+struct SecurityArch {
+    file_authentication_state: unsafe extern "efiapi" fn(
+        this: *const SecurityArch,
+        authentication_status: u32,
+        file: *const DevicePath,
+    ) -> Status,
+}
+
+/// `EFI_SECURITY2_ARCH_PROTOCOL`.
+#[repr(C)]
+#[unsafe_protocol("94ab2f58-1438-4ef1-9152-18941a3a0e68")]
+struct Security2Arch {
+    file_authentication: unsafe extern "efiapi" fn(
+        this: *const Security2Arch,
+        device_path: *const DevicePath,
+        file_buffer: *mut c_void,
+        file_size: usize,
+        boot_policy: bool,
+    ) -> Status,
+}
+
+// Cell that hands out a raw pointer to its contents instead of a reference, so
+// the module statics below never need `&mut <static>` (which trips the
+// `static_mut_refs` lint). Sound only because UEFI boot services are
+// single-threaded and a process may hold at most one [`SecurityOverride`] at a
+// time; the `INSTALLED` flag enforces the latter.
+struct Racy<T>(UnsafeCell<T>);
+
+// SAFETY: access is serialized by single-threaded boot services.
+unsafe impl<T> Sync for Racy<T> {}
+
+impl<T> Racy<T> {
+    const fn new(value: T) -> Self {
+        Self(UnsafeCell::new(value))
+    }
+
+    fn get(&self) -> *mut T {
+        self.0.get()
+    }
+}
+
+// Shim callbacks carry no user context, so the installed replacements reach the
+// active [`ShimLock`] and the saved originals through these statics.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static SHIM_LOCK: Racy<*const ShimLock> = Racy::new(ptr::null());
+static ORIGINAL_SECURITY: Racy<
+    Option<(
+        *mut SecurityArch,
+        unsafe extern "efiapi" fn(*const SecurityArch, u32, *const DevicePath) -> Status,
+    )>,
+> = Racy::new(None);
+static ORIGINAL_SECURITY2: Racy<
+    Option<(
+        *mut Security2Arch,
+        unsafe extern "efiapi" fn(
+            *const Security2Arch,
+            *const DevicePath,
+            *mut c_void,
+            usize,
+            bool,
+        ) -> Status,
+    )>,
+> = Racy::new(None);
+
+unsafe extern "efiapi" fn hook_security(
+    this: *const SecurityArch,
+    authentication_status: u32,
+    file: *const DevicePath,
+) -> Status {
+    // SAFETY: set by [`SecurityOverride::install`] before the pointers are
+    // swapped in, and cleared only after they are restored.
+    let Some((_, original)) = (unsafe { *ORIGINAL_SECURITY.get() }) else {
+        return Status::SECURITY_VIOLATION;
+    };
+    let original_status = unsafe { original(this, authentication_status, file) };
+    if original_status.is_success() {
+        return Status::SUCCESS;
+    }
+    // The `EFI_SECURITY_ARCH_PROTOCOL` handler is only handed a device path and
+    // no image bytes, so there is nothing to offer shim's buffer-based `verify`;
+    // only the `EFI_SECURITY2_ARCH_PROTOCOL` path is shim-backed. Defer to the
+    // firmware's original verdict here.
+    original_status
+}
+
+unsafe extern "efiapi" fn hook_security2(
+    this: *const Security2Arch,
+    device_path: *const DevicePath,
+    file_buffer: *mut c_void,
+    file_size: usize,
+    boot_policy: bool,
+) -> Status {
+    let Some((_, original)) = (unsafe { *ORIGINAL_SECURITY2.get() }) else {
+        return Status::SECURITY_VIOLATION;
+    };
+    let original_status =
+        unsafe { original(this, device_path, file_buffer, file_size, boot_policy) };
+    if original_status.is_success() {
+        return Status::SUCCESS;
+    }
+
+    // Fall back to shim: hand it the in-memory image and accept the load if
+    // shim's embedded certificate or the MOK list vouches for it.
+    let shim_lock = unsafe { *SHIM_LOCK.get() };
+    if shim_lock.is_null() || file_buffer.is_null() {
+        return original_status;
+    }
+    // SAFETY: the firmware guarantees `file_buffer`/`file_size` describe the
+    // image when it is present, and `SHIM_LOCK` outlives the override guard.
+    let buffer = unsafe { core::slice::from_raw_parts(file_buffer.cast::<u8>(), file_size) };
+    match unsafe { &*shim_lock }.verify(buffer) {
+        Ok(()) => Status::SUCCESS,
+        Err(_) => original_status,
+    }
+}
+
+/// RAII guard that routes the firmware's `LoadImage` authentication through
+/// [`ShimLock::verify`].
+///
+/// While this value is alive, the architectural security protocols try the
+/// firmware's original policy first and fall back to shim. Dropping it restores
+/// the original function pointers.
+///
+/// Only the buffer-based `EFI_SECURITY2_ARCH_PROTOCOL` path is backed by shim;
+/// the v1 `EFI_SECURITY_ARCH_PROTOCOL` handler receives a device path with no
+/// image bytes, so it defers to the firmware's original verdict.
+///
+/// The `'a` lifetime ties the guard to the borrowed [`ShimLock`]: the installed
+/// callbacks dereference it during firmware `LoadImage`, so the lock must
+/// outlive the guard.
+#[derive(Debug)]
+pub struct SecurityOverride<'a> {
+    // The restore state lives in the module statics; the guard owns the
+    // obligation to run [`Self::restore`] exactly once and borrows the
+    // [`ShimLock`] the installed hooks point at.
+    _shim_lock: PhantomData<&'a ShimLock>,
+}
+
+impl<'a> SecurityOverride<'a> {
+    /// Install the shim-backed security policy, saving the firmware's original
+    /// handlers.
+    ///
+    /// Returns [`Status::NOT_FOUND`] wrapped in an error if neither
+    /// architectural security protocol is present, or
+    /// [`Status::ALREADY_STARTED`] if an override is already installed —
+    /// capturing a second "original" pointer would record the hook itself and
+    /// corrupt the restore state.
+    pub fn install(shim_lock: &'a ShimLock) -> crate::Result<Self> {
+        // Refuse a second concurrent override; see the doc comment above.
+        if INSTALLED
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(Status::ALREADY_STARTED.into());
+        }
+
+        let mut installed = false;
+
+        if let Ok(handle) = boot::get_handle_for_protocol::<SecurityArch>() {
+            // SAFETY: architectural protocols are consumed, not owned; open with
+            // `GetProtocol` so the firmware and other consumers keep their
+            // access, matching how the crate reaches singleton protocols.
+            let proto = unsafe {
+                boot::open_protocol::<SecurityArch>(
+                    OpenProtocolParams {
+                        handle,
+                        agent: boot::image_handle(),
+                        controller: None,
+                    },
+                    OpenProtocolAttributes::GetProtocol,
+                )
+            };
+            if let Ok(mut proto) = proto {
+                let raw: *mut SecurityArch = &mut *proto;
+                // SAFETY: single-threaded boot services; see the statics above.
+                unsafe {
+                    *ORIGINAL_SECURITY.get() = Some((raw, (*raw).file_authentication_state));
+                    (*raw).file_authentication_state = hook_security;
+                }
+                installed = true;
+            }
+        }
+
+        if let Ok(handle) = boot::get_handle_for_protocol::<Security2Arch>() {
+            // SAFETY: see the `SecurityArch` open above.
+            let proto = unsafe {
+                boot::open_protocol::<Security2Arch>(
+                    OpenProtocolParams {
+                        handle,
+                        agent: boot::image_handle(),
+                        controller: None,
+                    },
+                    OpenProtocolAttributes::GetProtocol,
+                )
+            };
+            if let Ok(mut proto) = proto {
+                let raw: *mut Security2Arch = &mut *proto;
+                unsafe {
+                    *ORIGINAL_SECURITY2.get() = Some((raw, (*raw).file_authentication));
+                    (*raw).file_authentication = hook_security2;
+                }
+                installed = true;
+            }
+        }
+
+        if !installed {
+            INSTALLED.store(false, Ordering::Release);
+            return Err(Status::NOT_FOUND.into());
+        }
+
+        // SAFETY: see the statics above.
+        unsafe {
+            *SHIM_LOCK.get() = shim_lock;
+        }
+
+        Ok(Self {
+            _shim_lock: PhantomData,
+        })
+    }
+
+    fn restore(&self) {
+        // SAFETY: single-threaded boot services; the saved pointers were valid
+        // when captured and the protocols are not uninstalled while the guard
+        // is alive.
+        unsafe {
+            if let Some((raw, original)) = (*ORIGINAL_SECURITY.get()).take() {
+                (*raw).file_authentication_state = original;
+            }
+            if let Some((raw, original)) = (*ORIGINAL_SECURITY2.get()).take() {
+                (*raw).file_authentication = original;
+            }
+            *SHIM_LOCK.get() = ptr::null();
+        }
+        INSTALLED.store(false, Ordering::Release);
+    }
+}
+
+impl Drop for SecurityOverride<'_> {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}